@@ -30,16 +30,19 @@ impl FileReader {
 impl Future for FileReader {
     type Item = io::Result<String>;
 
-    fn poll(&mut self) -> Poll<Self::Item> {
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
         // If this is the first poll, spawn a thread that reads the path
         if self.thread.is_none() {
             let cloned_path = self.path.clone();
             let cloned_done_flag = Arc::clone(&self.done_flag);
+            let cloned_waker = waker.clone();
             let thread = thread::spawn(move || {
                 let str = fs::read_to_string(cloned_path);
                 // `Release` says everything beforehand must complete before
                 // we store `true` into the `done_flag`
                 cloned_done_flag.store(true, Ordering::Release);
+                // Tell the executor this future is ready to be re-polled.
+                cloned_waker.wake();
                 str
             });
             self.thread = Some(thread);
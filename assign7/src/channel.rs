@@ -0,0 +1,193 @@
+#![allow(dead_code, unused_imports)]
+
+/*
+ * Channel primitives whose receive end implements our `Future`, mirroring
+ * futures-channel's `oneshot` and `mpsc`. This gives futures spawned on
+ * `MultiThreadExecutor` a first-class way to hand results back instead of
+ * only producing `()`.
+ */
+
+use crate::future::{Future, Poll, Waker};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+pub mod oneshot {
+    use super::*;
+
+    // Both the value and the waker live behind one lock, so a `send` that
+    // races with a `poll` can't slip in between the receiver's "is there a
+    // value yet?" check and its "no, so register my waker" registration
+    // (which would otherwise store a value nobody wakes up to collect).
+    struct Inner<T> {
+        state: Mutex<State<T>>,
+    }
+
+    struct State<T> {
+        value: Option<T>,
+        waker: Option<Waker>,
+    }
+
+    pub struct Sender<T> {
+        inner: Arc<Inner<T>>,
+    }
+
+    pub struct Receiver<T> {
+        inner: Arc<Inner<T>>,
+    }
+
+    pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+        let inner = Arc::new(Inner {
+            state: Mutex::new(State {
+                value: None,
+                waker: None,
+            }),
+        });
+        (
+            Sender {
+                inner: Arc::clone(&inner),
+            },
+            Receiver { inner },
+        )
+    }
+
+    impl<T> Sender<T> {
+        /// Stores `t` for the receiver, waking it if it's already parked.
+        pub fn send(self, t: T) {
+            let mut state = self.inner.state.lock().expect("oneshot poisoned");
+            state.value = Some(t);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl<T> Future for Receiver<T>
+    where
+        T: Send,
+    {
+        type Item = T;
+
+        fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+            let mut state = self.inner.state.lock().expect("oneshot poisoned");
+            match state.value.take() {
+                Some(t) => Poll::Ready(t),
+                None => {
+                    state.waker = Some(waker.clone());
+                    Poll::NotReady
+                }
+            }
+        }
+    }
+}
+
+pub mod mpsc {
+    use super::*;
+
+    // The queue, the waker, and the live-sender count all live behind one
+    // lock. That's what lets the receiver's "is there an item? no, is
+    // every sender gone? no, so register my waker" sequence in `poll` and
+    // the sender's "decrement the count, and if that was the last one,
+    // wake the receiver" sequence in `Drop` serialize against each other;
+    // with separate locks a `Drop` could slip in between the two checks
+    // and wake a waker that isn't registered yet, leaving `poll` parked
+    // forever even though every sender is gone.
+    struct Inner<T> {
+        state: Mutex<State<T>>,
+    }
+
+    struct State<T> {
+        queue: VecDeque<T>,
+        waker: Option<Waker>,
+        senders: usize,
+    }
+
+    pub struct Sender<T> {
+        inner: Arc<Inner<T>>,
+    }
+
+    pub struct Receiver<T> {
+        inner: Arc<Inner<T>>,
+    }
+
+    /// Creates an unbounded channel. Unlike futures-channel's
+    /// `mpsc::channel`, this one takes no `capacity`: `Sender::send` never
+    /// blocks, so a parameter promising a bound would be misleading.
+    pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+        let inner = Arc::new(Inner {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                waker: None,
+                senders: 1,
+            }),
+        });
+        (
+            Sender {
+                inner: Arc::clone(&inner),
+            },
+            Receiver { inner },
+        )
+    }
+
+    impl<T> Clone for Sender<T> {
+        fn clone(&self) -> Sender<T> {
+            self.inner.state.lock().expect("mpsc poisoned").senders += 1;
+            Sender {
+                inner: Arc::clone(&self.inner),
+            }
+        }
+    }
+
+    impl<T> Sender<T> {
+        pub fn send(&self, t: T) {
+            let mut state = self.inner.state.lock().expect("mpsc poisoned");
+            state.queue.push_back(t);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl<T> Drop for Sender<T> {
+        fn drop(&mut self) {
+            let mut state = self.inner.state.lock().expect("mpsc poisoned");
+            state.senders -= 1;
+            if state.senders == 0 {
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+
+    // The future returned by `Receiver::recv`.
+    pub struct Recv<'a, T> {
+        receiver: &'a mut Receiver<T>,
+    }
+
+    impl<T> Receiver<T> {
+        /// Returns a future that resolves to the next item, or `None` once
+        /// every `Sender` has dropped.
+        pub fn recv(&mut self) -> Recv<'_, T> {
+            Recv { receiver: self }
+        }
+    }
+
+    impl<'a, T> Future for Recv<'a, T>
+    where
+        T: Send,
+    {
+        type Item = Option<T>;
+
+        fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+            let mut state = self.receiver.inner.state.lock().expect("mpsc poisoned");
+            if let Some(t) = state.queue.pop_front() {
+                return Poll::Ready(Some(t));
+            }
+            if state.senders == 0 {
+                return Poll::Ready(None);
+            }
+            state.waker = Some(waker.clone());
+            Poll::NotReady
+        }
+    }
+}
@@ -1,9 +1,12 @@
 #![allow(dead_code, unused_imports, unused_variables, unused_mut)]
 
-use crate::future::{Future, Poll};
+use crate::channel::oneshot;
+use crate::future::{map, Future, Poll, PriorityKind, ReadyQueue, Waker};
 use crate::future_util::*;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
 use std::mem;
-use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
 use std::thread;
 
 /*
@@ -15,6 +18,41 @@ pub trait Executor {
     where
         F: Future<Item = ()> + 'static;
     fn wait(&mut self);
+
+    /// Spawns `f` and returns a `RemoteHandle` to its eventual result,
+    /// instead of throwing the output away like a plain `spawn`. Dropping
+    /// the handle lets `f` keep running detached.
+    fn spawn_with_handle<F>(&mut self, f: F) -> RemoteHandle<F::Item>
+    where
+        F: Future + 'static,
+        F::Item: 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        // The wrapped future is still `Future<Item = ()>`, so it fits the
+        // existing `spawn` machinery; the result travels back over `tx`.
+        let wrapped = map(f, move |item| tx.send(item));
+        self.spawn(wrapped);
+        RemoteHandle { receiver: rx }
+    }
+}
+
+/// A handle to a still-running task's eventual result. It's itself a
+/// `Future`, so the caller can poll it (or block on it, e.g. via
+/// `BlockingExecutor`) later to retrieve the value. Dropping the handle
+/// just lets the task keep running detached; it doesn't cancel it.
+pub struct RemoteHandle<T> {
+    receiver: oneshot::Receiver<T>,
+}
+
+impl<T> Future for RemoteHandle<T>
+where
+    T: Send,
+{
+    type Item = T;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        self.receiver.poll(waker)
+    }
 }
 
 /*
@@ -35,8 +73,11 @@ impl Executor for BlockingExecutor {
     where
         F: Future<Item = ()>,
     {
+        // `BlockingExecutor` doesn't track task ids to park on, so it just
+        // keeps re-polling with a no-op waker until the future is done.
+        let waker = Waker::noop();
         loop {
-            if let Poll::Ready(()) = f.poll() {
+            if let Poll::Ready(()) = f.poll(&waker) {
                 break;
             }
         }
@@ -50,103 +91,257 @@ impl Executor for BlockingExecutor {
  */
 
 pub struct SingleThreadExecutor {
-    futures: Vec<Box<dyn Future<Item = ()>>>,
+    // `None` marks a slot whose future has already completed.
+    tasks: Vec<Option<Box<dyn Future<Item = ()>>>>,
+    priorities: Vec<u8>,
+    ready_queue: Arc<ReadyQueue>,
 }
 
 impl SingleThreadExecutor {
     pub fn new() -> SingleThreadExecutor {
-        SingleThreadExecutor { futures: vec![] }
+        SingleThreadExecutor::with_priority_kind(PriorityKind::Max)
     }
-}
 
-impl Executor for SingleThreadExecutor {
-    fn spawn<F>(&mut self, mut f: F)
+    /// Like `new`, but lets the caller choose whether a bigger or a smaller
+    /// `u8` counts as more urgent for `spawn_with_priority`.
+    pub fn with_priority_kind(kind: PriorityKind) -> SingleThreadExecutor {
+        SingleThreadExecutor {
+            tasks: vec![],
+            priorities: vec![],
+            ready_queue: Arc::new(ReadyQueue::new(kind)),
+        }
+    }
+
+    // The priority plain `spawn` defaults to: least urgent for whichever
+    // `PriorityKind` this executor was built with.
+    fn lowest_priority(&self) -> u8 {
+        match self.ready_queue.kind() {
+            PriorityKind::Max => 0,
+            PriorityKind::Min => u8::MAX,
+        }
+    }
+
+    /// Spawns `f` at the given priority; higher-priority ready tasks are
+    /// always polled before lower-priority ones.
+    pub fn spawn_with_priority<F>(&mut self, mut f: F, priority: u8)
     where
         F: Future<Item = ()> + 'static,
     {
-        match f.poll() {
-            Poll::NotReady => self.futures.push(Box::new(f)),
+        let task_id = self.tasks.len();
+        // Reserve the slot before the first poll, since the future may wake
+        // itself (e.g. from another thread) before we've pushed it.
+        self.tasks.push(None);
+        self.priorities.push(priority);
+        let waker = self.waker_for(task_id);
+        match f.poll(&waker) {
+            Poll::NotReady => self.tasks[task_id] = Some(Box::new(f)),
             Poll::Ready(_) => (),
         }
     }
 
+    fn waker_for(&self, task_id: usize) -> Waker {
+        Waker::new(task_id, self.priorities[task_id], Arc::clone(&self.ready_queue))
+    }
+}
+
+impl Executor for SingleThreadExecutor {
+    fn spawn<F>(&mut self, f: F)
+    where
+        F: Future<Item = ()> + 'static,
+    {
+        let priority = self.lowest_priority();
+        self.spawn_with_priority(f, priority);
+    }
+
     fn wait(&mut self) {
-        let n = self.futures.len();
-        let mut num_completed = 0;
+        let n = self.tasks.len();
+        let mut num_completed = self.tasks.iter().filter(|t| t.is_none()).count();
+        // Instead of spin-polling every task every iteration, block on the
+        // ready queue and only re-poll the highest-priority tasks that were
+        // actually woken.
         while num_completed < n {
-            for (i, fut) in self.futures.iter_mut().enumerate() {
-                if let Poll::Ready(_) = fut.poll() {
-                    num_completed += 1;
-                    continue;
+            for task_id in self.ready_queue.wait_for_ready() {
+                let waker = self.waker_for(task_id);
+                if let Some(fut) = &mut self.tasks[task_id] {
+                    if let Poll::Ready(_) = fut.poll(&waker) {
+                        self.tasks[task_id] = None;
+                        num_completed += 1;
+                    }
                 }
             }
         }
-        self.futures.clear()
+        self.tasks.clear();
+        self.priorities.clear();
+    }
+}
+
+/*
+ * Part 2b/c - Multi threaded executor with priority dispatch
+ */
+
+// A future waiting to be picked up by a worker thread, ordered by priority
+// and, for ties, by submission order (earlier submissions sort first).
+struct PendingTask {
+    key: u8,
+    seq: usize,
+    future: Box<dyn Future<Item = ()>>,
+}
+
+impl PartialEq for PendingTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.seq == other.seq
+    }
+}
+
+impl Eq for PendingTask {}
+
+impl Ord for PendingTask {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key.cmp(&other.key).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for PendingTask {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Shared dispatch queue the worker threads pop from; like `ReadyQueue`, but
+// carrying the futures themselves instead of just task ids, and with an
+// explicit shutdown signal instead of a sentinel value.
+struct TaskQueue {
+    kind: PriorityKind,
+    state: Mutex<TaskQueueState>,
+    condvar: Condvar,
+}
+
+struct TaskQueueState {
+    heap: BinaryHeap<PendingTask>,
+    next_seq: usize,
+    shutdown: bool,
+}
+
+impl TaskQueue {
+    fn new(kind: PriorityKind) -> TaskQueue {
+        TaskQueue {
+            kind,
+            state: Mutex::new(TaskQueueState {
+                heap: BinaryHeap::new(),
+                next_seq: 0,
+                shutdown: false,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn key(&self, priority: u8) -> u8 {
+        match self.kind {
+            PriorityKind::Max => priority,
+            PriorityKind::Min => u8::MAX - priority,
+        }
+    }
+
+    fn push(&self, priority: u8, future: Box<dyn Future<Item = ()>>) {
+        let mut state = self.state.lock().expect("task queue poisoned");
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.heap.push(PendingTask {
+            key: self.key(priority),
+            seq,
+            future,
+        });
+        self.condvar.notify_one();
+    }
+
+    fn shutdown(&self) {
+        let mut state = self.state.lock().expect("task queue poisoned");
+        state.shutdown = true;
+        self.condvar.notify_all();
+    }
+
+    /// Blocks until there's a task to run or every submitter has shut down.
+    fn pop(&self) -> Option<Box<dyn Future<Item = ()>>> {
+        let mut state = self.state.lock().expect("task queue poisoned");
+        loop {
+            if let Some(task) = state.heap.pop() {
+                return Some(task.future);
+            }
+            if state.shutdown {
+                return None;
+            }
+            state = self.condvar.wait(state).expect("task queue poisoned");
+        }
     }
 }
 
 pub struct MultiThreadExecutor {
-    sender: mpsc::Sender<Option<Box<dyn Future<Item = ()>>>>,
+    queue: Arc<TaskQueue>,
     threads: Vec<thread::JoinHandle<()>>,
 }
 
 impl MultiThreadExecutor {
     pub fn new(num_threads: i32) -> MultiThreadExecutor {
-        let (sender, rcvr) = mpsc::channel();
-        // Wrap the receiver in `Arc<Mutex<_>>` so it can be shared across thread
-        let receiver = Arc::new(Mutex::new(rcvr));
+        MultiThreadExecutor::with_priority_kind(num_threads, PriorityKind::Max)
+    }
+
+    /// Like `new`, but lets the caller choose whether a bigger or a smaller
+    /// `u8` counts as more urgent for `spawn_with_priority`.
+    pub fn with_priority_kind(num_threads: i32, kind: PriorityKind) -> MultiThreadExecutor {
+        let queue = Arc::new(TaskQueue::new(kind));
         let mut threads: Vec<thread::JoinHandle<()>> = Vec::new();
         for _ in 0..num_threads {
-            let thread_receiver = Arc::clone(&receiver);
+            let thread_queue = Arc::clone(&queue);
             // We need the `move` keyword in front of the closure so that the closure
-            // take ownership of `thread_receiver`
+            // take ownership of `thread_queue`
             let thread_handle = thread::spawn(move || {
                 // Create a thread-local executor
                 let mut local_executor = SingleThreadExecutor::new();
-                loop {
-                    // Loop receiving futures form the channel
-                    let future: Option<Box<dyn Future<Item = ()>>> = thread_receiver
-                        .lock()
-                        .expect("Failed to acquire lock")
-                        .recv()
-                        .expect("Channel closed unexpectedly");
-                    if let Some(fut) = future {
-                        // Got a future, spawn it on local executor
-                        local_executor.spawn(fut);
-                    } else {
-                        // Wait on their single-thread executor when we receive `None`,
-                        // then shutdown afterwards
-                        local_executor.wait();
-                        break;
-                    }
+                // Pull the highest-priority pending future each time, until
+                // the queue is drained and shut down, then drive whatever
+                // this thread picked up to completion.
+                while let Some(future) = thread_queue.pop() {
+                    local_executor.spawn(future);
                 }
+                local_executor.wait();
             });
             threads.push(thread_handle)
         }
-        MultiThreadExecutor { sender, threads }
+        MultiThreadExecutor { queue, threads }
+    }
+
+    fn lowest_priority(&self) -> u8 {
+        match self.queue.kind {
+            PriorityKind::Max => 0,
+            PriorityKind::Min => u8::MAX,
+        }
+    }
+
+    /// Dispatches `f` to whichever worker thread is next free, at the given
+    /// priority; higher-priority pending futures are always picked up
+    /// before lower-priority ones.
+    pub fn spawn_with_priority<F>(&mut self, f: F, priority: u8)
+    where
+        F: Future<Item = ()> + 'static,
+    {
+        self.queue.push(priority, Box::new(f));
     }
 }
 
 impl Executor for MultiThreadExecutor {
-    /// Spawning a future sends the future over a channel
+    /// Spawning a future sends the future over the shared dispatch queue
     fn spawn<F>(&mut self, f: F)
     where
         F: Future<Item = ()> + 'static,
     {
-        let future: Option<Box<dyn Future<Item = ()>>> = Some(Box::new(f));
-        self.sender
-            .send(future)
-            .expect("Failed to send future to worker thread");
+        let priority = self.lowest_priority();
+        self.spawn_with_priority(f, priority);
     }
 
     fn wait(&mut self) {
-        let n = self.threads.len();
-        // Send `None` to each thread as a shutdown signal
-        for _ in 0..n {
-            self.sender
-                .send(None)
-                .expect("Failed to send shutdown signal");
-        }
+        // Tell the dispatch queue no more futures are coming, so workers
+        // that run dry can stop waiting and shut down.
+        self.queue.shutdown();
         // Take ownership of `self.threads`, then join all the worker thread
         take_mut::take(&mut self.threads, |threads| {
             for t in threads {
@@ -1,5 +1,9 @@
 #![allow(dead_code, unused_imports)]
 
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use take_mut;
 
 /*
@@ -14,7 +18,218 @@ pub enum Poll<T> {
 
 pub trait Future: Send {
     type Item: Send;
-    fn poll(&mut self) -> Poll<Self::Item>;
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item>;
+}
+
+// Lets a boxed trait object (e.g. `Box<dyn Future<Item = ()>>`, as stored
+// by `SingleThreadExecutor` and `MultiThreadExecutor`) be polled like any
+// other future.
+impl<F> Future for Box<F>
+where
+    F: Future + ?Sized,
+{
+    type Item = F::Item;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        (**self).poll(waker)
+    }
+}
+
+/*
+ * Waker-based notification, so executors can park instead of busy-polling.
+ *
+ * A `Waker` is handed to every `poll` call. Leaf futures (e.g. `FileReader`)
+ * that can't make progress yet should clone it and call `wake()` from
+ * whatever thread eventually finishes the work; that pushes the
+ * corresponding task id onto the executor's ready queue and notifies
+ * anyone parked on it, so the executor only re-polls tasks that actually
+ * have something to do.
+ */
+
+/// Which end of the priority range is most urgent: `Max` means bigger
+/// numbers run first, `Min` means smaller numbers run first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityKind {
+    Min,
+    Max,
+}
+
+// A task id waiting in a `ReadyQueue`, ordered by priority and, for ties,
+// by insertion order (earlier insertions sort first).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PrioritizedTask {
+    key: u8,
+    seq: usize,
+    task_id: usize,
+}
+
+impl Ord for PrioritizedTask {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key
+            .cmp(&other.key)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for PrioritizedTask {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A priority queue of task ids that are ready to be re-polled, plus a
+/// condvar so an executor can block until one shows up instead of
+/// spinning. The highest-priority ready task (per `kind`) always comes out
+/// of `wait_for_ready` first.
+pub struct ReadyQueue {
+    kind: PriorityKind,
+    state: Mutex<ReadyQueueState>,
+    condvar: Condvar,
+}
+
+struct ReadyQueueState {
+    heap: BinaryHeap<PrioritizedTask>,
+    next_seq: usize,
+}
+
+impl ReadyQueue {
+    pub fn new(kind: PriorityKind) -> ReadyQueue {
+        ReadyQueue {
+            kind,
+            state: Mutex::new(ReadyQueueState {
+                heap: BinaryHeap::new(),
+                next_seq: 0,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    pub fn kind(&self) -> PriorityKind {
+        self.kind
+    }
+
+    // Translates a task's priority into a heap key where "bigger pops
+    // first" always means "more urgent", regardless of `kind`.
+    fn key(&self, priority: u8) -> u8 {
+        match self.kind {
+            PriorityKind::Max => priority,
+            PriorityKind::Min => u8::MAX - priority,
+        }
+    }
+
+    /// Marks `task_id` ready at `priority` and wakes up anyone parked in
+    /// `wait_for_ready`.
+    pub fn push(&self, task_id: usize, priority: u8) {
+        let mut state = self.state.lock().expect("ready queue poisoned");
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.heap.push(PrioritizedTask {
+            key: self.key(priority),
+            seq,
+            task_id,
+        });
+        self.condvar.notify_one();
+    }
+
+    /// Blocks until at least one task is ready, then drains and returns
+    /// every ready task id, highest priority first.
+    pub fn wait_for_ready(&self) -> Vec<usize> {
+        let mut state = self.state.lock().expect("ready queue poisoned");
+        while state.heap.is_empty() {
+            state = self.condvar.wait(state).expect("ready queue poisoned");
+        }
+        let mut ready = Vec::with_capacity(state.heap.len());
+        while let Some(task) = state.heap.pop() {
+            ready.push(task.task_id);
+        }
+        ready
+    }
+}
+
+/// The state backing a `Waker`: which task to mark ready (and at what
+/// priority), where to report that, and whether it's already been woken.
+struct WakerRegistration {
+    task_id: usize,
+    priority: u8,
+    ready_queue: Option<Arc<ReadyQueue>>,
+    woken: AtomicBool,
+}
+
+/// Handle that leaf futures store and call once they become ready, so the
+/// executor that owns their task can stop busy-polling and park until
+/// there's real work to do.
+#[derive(Clone)]
+pub struct Waker(Arc<WakerRegistration>);
+
+impl Waker {
+    /// A waker bound to `task_id` (scheduled at `priority`) in
+    /// `ready_queue`'s executor.
+    pub fn new(task_id: usize, priority: u8, ready_queue: Arc<ReadyQueue>) -> Waker {
+        Waker(Arc::new(WakerRegistration {
+            task_id,
+            priority,
+            ready_queue: Some(ready_queue),
+            woken: AtomicBool::new(false),
+        }))
+    }
+
+    /// A waker that isn't tied to any executor. Used by executors like
+    /// `BlockingExecutor` that just re-poll in a tight loop and don't need
+    /// real notifications.
+    pub fn noop() -> Waker {
+        Waker(Arc::new(WakerRegistration {
+            task_id: 0,
+            priority: 0,
+            ready_queue: None,
+            woken: AtomicBool::new(true),
+        }))
+    }
+
+    /// Whether `wake()` has been called on this waker (or a clone of it).
+    /// A `Waker::noop()` always reports `true`.
+    pub fn woken(&self) -> bool {
+        self.0.woken.load(Ordering::Acquire)
+    }
+
+    /// Marks the underlying task ready, pushing it onto its executor's
+    /// ready queue so a parked `wait` wakes up and re-polls it.
+    pub fn wake(&self) {
+        self.0.woken.store(true, Ordering::Release);
+        if let Some(ready_queue) = &self.0.ready_queue {
+            ready_queue.push(self.0.task_id, self.0.priority);
+        }
+    }
+}
+
+/*
+ * Compatibility shim for futures that don't (yet) register a waker and
+ * instead expect to be busy-polled: every `NotReady` immediately re-wakes
+ * its own task, so it keeps getting re-polled under the new executors
+ * instead of stalling forever.
+ */
+
+struct BusyPoll<F>(F);
+
+pub fn busy_poll<F>(f: F) -> impl Future<Item = F::Item>
+where
+    F: Future,
+{
+    BusyPoll(f)
+}
+
+impl<F> Future for BusyPoll<F>
+where
+    F: Future,
+{
+    type Item = F::Item;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        let result = self.0.poll(waker);
+        if let Poll::NotReady = result {
+            waker.wake();
+        }
+        result
+    }
 }
 
 /*
@@ -43,7 +258,7 @@ where
 {
     type Item = T;
 
-    fn poll(&mut self) -> Poll<Self::Item> {
+    fn poll(&mut self, _waker: &Waker) -> Poll<Self::Item> {
         Poll::Ready(self.t.take().unwrap())
     }
 }
@@ -78,8 +293,8 @@ where
 {
     type Item = T;
 
-    fn poll(&mut self) -> Poll<Self::Item> {
-        match self.fut.poll() {
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        match self.fut.poll(waker) {
             Poll::NotReady => Poll::NotReady,
             Poll::Ready(s) => {
                 let f: Option<Fun> = self.fun.take();
@@ -88,137 +303,3 @@ where
         }
     }
 }
-
-/*
- * Part 1a - Join
- */
-
-// A join of two futures is a state machine depending on which future is
-// completed, represented as an enum.
-pub enum Join<F, G>
-where
-    F: Future,
-    G: Future,
-{
-    BothRunning(F, G),
-    FirstDone(F::Item, G),
-    SecondDone(F, G::Item),
-    Done,
-}
-
-// When a join is created, we start by assuming neither child future
-// has completed.
-pub fn join<F, G>(f: F, g: G) -> impl Future<Item = (F::Item, G::Item)>
-where
-    F: Future,
-    G: Future,
-{
-    Join::BothRunning(f, g)
-}
-
-impl<F, G> Future for Join<F, G>
-where
-    F: Future,
-    G: Future,
-{
-    type Item = (F::Item, G::Item);
-
-    fn poll(&mut self) -> Poll<Self::Item> {
-        // Since we can't return the `Poll` result directly inside
-        // the `take` closure, we have to store it in a mutable variable,
-        // which we'll modify inside the closure
-        let mut poll_result = Poll::NotReady;
-
-        take_mut::take(self, |this| match this {
-            Join::FirstDone(f_item, mut g) => match g.poll() {
-                Poll::Ready(g_item) => {
-                    poll_result = Poll::Ready((f_item, g_item));
-                    Join::Done
-                }
-                Poll::NotReady => Join::FirstDone(f_item, g),
-            },
-            Join::SecondDone(mut f, g_item) => match f.poll() {
-                Poll::Ready(f_item) => {
-                    poll_result = Poll::Ready((f_item, g_item));
-                    Join::Done
-                }
-                Poll::NotReady => Join::SecondDone(f, g_item),
-            },
-            Join::BothRunning(mut f, mut g) => match (f.poll(), g.poll()) {
-                (Poll::Ready(f_item), Poll::Ready(g_item)) => {
-                    poll_result = Poll::Ready((f_item, g_item));
-                    Join::Done
-                }
-                (Poll::Ready(f_item), Poll::NotReady) => Join::FirstDone(f_item, g),
-                (Poll::NotReady, Poll::Ready(g_item)) => Join::SecondDone(f, g_item),
-                (Poll::NotReady, Poll::NotReady) => Join::BothRunning(f, g),
-            },
-            Join::Done => panic!("poll called after future completed"),
-        });
-        poll_result
-    }
-}
-
-/*
- * Part 1b - AndThen
- */
-
-// The AndThen state machine depends on which future is currently running.
-pub enum AndThen<Fut1, Fut2, Fun> {
-    First(Fut1, Fun),
-    Second(Fut2),
-    Done,
-}
-
-pub fn and_then<Fut1, Fut2, Fun>(fut: Fut1, fun: Fun) -> impl Future<Item = Fut2::Item>
-where
-    Fut1: Future,
-    Fut2: Future,
-    Fun: FnOnce(Fut1::Item) -> Fut2 + Send,
-{
-    AndThen::First(fut, fun)
-}
-
-impl<Fut1, Fut2, Fun> Future for AndThen<Fut1, Fut2, Fun>
-where
-    Fut1: Future,
-    Fut2: Future,
-    Fun: FnOnce(Fut1::Item) -> Fut2 + Send,
-{
-    type Item = Fut2::Item;
-
-    fn poll(&mut self) -> Poll<Self::Item> {
-        let mut poll_result = Poll::NotReady;
-
-        take_mut::take(self, |this| {
-            match this {
-                // Note the use of the `mut` identifier pattern,
-                // which allows the call `first.poll()` to be possible
-                // (since it requires mutable ownership of `first`)
-                AndThen::First(mut first, f) => {
-                    match first.poll() {
-                        Poll::Ready(item) => {
-                            let mut second = f(item);
-                            // Recursively poll the new future
-                            poll_result = second.poll();
-                            AndThen::Done
-                        }
-                        Poll::NotReady => AndThen::First(first, f),
-                    }
-                }
-                AndThen::Second(mut second) => match second.poll() {
-                    Poll::Ready(item) => {
-                        poll_result = Poll::Ready(item);
-                        AndThen::Done
-                    }
-                    Poll::NotReady => AndThen::Second(second),
-                },
-                AndThen::Done => {
-                    panic!("poll called after future completed")
-                }
-            }
-        });
-
-        poll_result
-    }
-}
@@ -0,0 +1,301 @@
+#![allow(dead_code, unused_imports)]
+
+use crate::future::{Future, Poll, Waker};
+use std::mem;
+use take_mut;
+
+/*
+ * Part 1a - Join
+ */
+
+// A join of two futures is a state machine depending on which future is
+// completed, represented as an enum.
+pub enum Join<F, G>
+where
+    F: Future,
+    G: Future,
+{
+    BothRunning(F, G),
+    FirstDone(F::Item, G),
+    SecondDone(F, G::Item),
+    Done,
+}
+
+// When a join is created, we start by assuming neither child future
+// has completed.
+pub fn join<F, G>(f: F, g: G) -> impl Future<Item = (F::Item, G::Item)>
+where
+    F: Future,
+    G: Future,
+{
+    Join::BothRunning(f, g)
+}
+
+impl<F, G> Future for Join<F, G>
+where
+    F: Future,
+    G: Future,
+{
+    type Item = (F::Item, G::Item);
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        // Since we can't return the `Poll` result directly inside
+        // the `take` closure, we have to store it in a mutable variable,
+        // which we'll modify inside the closure
+        let mut poll_result = Poll::NotReady;
+
+        take_mut::take(self, |this| match this {
+            Join::FirstDone(f_item, mut g) => match g.poll(waker) {
+                Poll::Ready(g_item) => {
+                    poll_result = Poll::Ready((f_item, g_item));
+                    Join::Done
+                }
+                Poll::NotReady => Join::FirstDone(f_item, g),
+            },
+            Join::SecondDone(mut f, g_item) => match f.poll(waker) {
+                Poll::Ready(f_item) => {
+                    poll_result = Poll::Ready((f_item, g_item));
+                    Join::Done
+                }
+                Poll::NotReady => Join::SecondDone(f, g_item),
+            },
+            Join::BothRunning(mut f, mut g) => match (f.poll(waker), g.poll(waker)) {
+                (Poll::Ready(f_item), Poll::Ready(g_item)) => {
+                    poll_result = Poll::Ready((f_item, g_item));
+                    Join::Done
+                }
+                (Poll::Ready(f_item), Poll::NotReady) => Join::FirstDone(f_item, g),
+                (Poll::NotReady, Poll::Ready(g_item)) => Join::SecondDone(f, g_item),
+                (Poll::NotReady, Poll::NotReady) => Join::BothRunning(f, g),
+            },
+            Join::Done => panic!("poll called after future completed"),
+        });
+        poll_result
+    }
+}
+
+/*
+ * Part 1b - AndThen
+ */
+
+// The AndThen state machine depends on which future is currently running.
+pub enum AndThen<Fut1, Fut2, Fun> {
+    First(Fut1, Fun),
+    Second(Fut2),
+    Done,
+}
+
+pub fn and_then<Fut1, Fut2, Fun>(fut: Fut1, fun: Fun) -> impl Future<Item = Fut2::Item>
+where
+    Fut1: Future,
+    Fut2: Future,
+    Fun: FnOnce(Fut1::Item) -> Fut2 + Send,
+{
+    AndThen::First(fut, fun)
+}
+
+impl<Fut1, Fut2, Fun> Future for AndThen<Fut1, Fut2, Fun>
+where
+    Fut1: Future,
+    Fut2: Future,
+    Fun: FnOnce(Fut1::Item) -> Fut2 + Send,
+{
+    type Item = Fut2::Item;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        let mut poll_result = Poll::NotReady;
+
+        take_mut::take(self, |this| {
+            match this {
+                // Note the use of the `mut` identifier pattern,
+                // which allows the call `first.poll()` to be possible
+                // (since it requires mutable ownership of `first`)
+                AndThen::First(mut first, f) => {
+                    match first.poll(waker) {
+                        Poll::Ready(item) => {
+                            let mut second = f(item);
+                            // Recursively poll the new future
+                            match second.poll(waker) {
+                                Poll::Ready(item) => {
+                                    poll_result = Poll::Ready(item);
+                                    AndThen::Done
+                                }
+                                Poll::NotReady => AndThen::Second(second),
+                            }
+                        }
+                        Poll::NotReady => AndThen::First(first, f),
+                    }
+                }
+                AndThen::Second(mut second) => match second.poll(waker) {
+                    Poll::Ready(item) => {
+                        poll_result = Poll::Ready(item);
+                        AndThen::Done
+                    }
+                    Poll::NotReady => AndThen::Second(second),
+                },
+                AndThen::Done => {
+                    panic!("poll called after future completed")
+                }
+            }
+        });
+
+        poll_result
+    }
+}
+
+/*
+ * select - races two futures, resolving as soon as either one finishes and
+ * handing the still-running one back to the caller.
+ */
+
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+pub enum Select<F, G> {
+    BothRunning(F, G),
+    Done,
+}
+
+pub fn select<F, G>(f: F, g: G) -> impl Future<Item = Either<(F::Item, G), (F, G::Item)>>
+where
+    F: Future,
+    G: Future,
+{
+    Select::BothRunning(f, g)
+}
+
+impl<F, G> Future for Select<F, G>
+where
+    F: Future,
+    G: Future,
+{
+    type Item = Either<(F::Item, G), (F, G::Item)>;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        let mut poll_result = Poll::NotReady;
+
+        take_mut::take(self, |this| match this {
+            Select::BothRunning(mut f, mut g) => {
+                // Poll the left branch first, so a tie (both ready on the
+                // same poll) breaks toward `f`.
+                match f.poll(waker) {
+                    Poll::Ready(f_item) => {
+                        poll_result = Poll::Ready(Either::Left((f_item, g)));
+                        Select::Done
+                    }
+                    Poll::NotReady => match g.poll(waker) {
+                        Poll::Ready(g_item) => {
+                            poll_result = Poll::Ready(Either::Right((f, g_item)));
+                            Select::Done
+                        }
+                        Poll::NotReady => Select::BothRunning(f, g),
+                    },
+                }
+            }
+            Select::Done => panic!("poll called after future completed"),
+        });
+
+        poll_result
+    }
+}
+
+/*
+ * join_all / FuturesUnordered - await a dynamic collection of futures and
+ * collect their items in the order they complete.
+ */
+
+pub struct Unordered<F>
+where
+    F: Future,
+{
+    // `None` marks a slot whose future has already completed.
+    slots: Vec<Option<F>>,
+    remaining: usize,
+    results: Vec<F::Item>,
+}
+
+pub fn join_all<F>(futures: Vec<F>) -> impl Future<Item = Vec<F::Item>>
+where
+    F: Future,
+{
+    let remaining = futures.len();
+    Unordered {
+        slots: futures.into_iter().map(Some).collect(),
+        remaining,
+        results: Vec::new(),
+    }
+}
+
+impl<F> Future for Unordered<F>
+where
+    F: Future,
+{
+    type Item = Vec<F::Item>;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        // Sweep every still-pending slot, stashing the item of any future
+        // that's ready and leaving `None` in its place.
+        for slot in self.slots.iter_mut() {
+            if let Some(mut fut) = slot.take() {
+                match fut.poll(waker) {
+                    Poll::Ready(item) => {
+                        self.results.push(item);
+                        self.remaining -= 1;
+                    }
+                    Poll::NotReady => *slot = Some(fut),
+                }
+            }
+        }
+
+        if self.remaining == 0 {
+            Poll::Ready(mem::take(&mut self.results))
+        } else {
+            Poll::NotReady
+        }
+    }
+}
+
+/*
+ * fuse - makes a future safe to poll after it's already completed, instead
+ * of panicking (or re-running the inner future) like `Join`/`AndThen` do.
+ */
+
+pub enum Fuse<F> {
+    Active(F),
+    Finished,
+}
+
+pub fn fuse<F>(f: F) -> impl Future<Item = F::Item>
+where
+    F: Future,
+{
+    Fuse::Active(f)
+}
+
+impl<F> Future for Fuse<F>
+where
+    F: Future,
+{
+    type Item = F::Item;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Item> {
+        let mut poll_result = Poll::NotReady;
+
+        take_mut::take(self, |this| match this {
+            Fuse::Active(mut f) => match f.poll(waker) {
+                Poll::Ready(item) => {
+                    poll_result = Poll::Ready(item);
+                    Fuse::Finished
+                }
+                Poll::NotReady => Fuse::Active(f),
+            },
+            // Already done: stay `NotReady` forever instead of panicking
+            // or re-polling the inner future.
+            Fuse::Finished => Fuse::Finished,
+        });
+
+        poll_result
+    }
+}
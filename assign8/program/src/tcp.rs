@@ -34,22 +34,25 @@ fn get_seq_nums(packets: &Vec<Packet>) -> Vec<usize> {
   packets.iter().map(|pk| pk.seqno).collect()
 }
 
-fn get_buffers(packets: &Vec<Packet>) -> Vec<Buffer> {
-  packets.iter().map(|pk| pk.buf.clone()).collect()
-}
-
 pub fn tcp_server(c: Chan<(), TCPServer>) -> Vec<Buffer> {
   // Handshake
   let (c, syn) = c.recv();
   let c = c.send(SynAck);
   let (c, ack) = c.recv();
 
-  // Data transfer
+  // Data transfer: since a round may only deliver some of the client's
+  // packets (`NOISY` mode drops ~40% of them), accumulate everything seen
+  // by `seqno` across every retransmission round instead of keeping just
+  // the last one.
+  let mut received: HashMap<usize, Buffer> = HashMap::new();
   let mut c = c.rec_push();
   loop {
     c = {
       // Data transfer process
-      let (c, mut packets) = c.recv();
+      let (c, packets) = c.recv();
+      for packet in &packets {
+        received.insert(packet.seqno, packet.buf.clone());
+      }
       let seq_nums = get_seq_nums(&packets);
       let c = c.send(seq_nums);
 
@@ -61,12 +64,10 @@ pub fn tcp_server(c: Chan<(), TCPServer>) -> Vec<Buffer> {
           let (c, ack) = c.recv();
           c.close();
 
-          // Sort packets in increasing order of `seqno`
-          packets.sort_by(|p1, p2| p1.seqno.cmp(&p2.seqno));
-
-          // Project out all the buffers
-          let buffers = get_buffers(&packets);
-          return buffers;
+          // Project out buffers in increasing order of `seqno`
+          let mut entries: Vec<(usize, Buffer)> = received.into_iter().collect();
+          entries.sort_by(|(seqno1, _), (seqno2, _)| seqno1.cmp(seqno2));
+          return entries.into_iter().map(|(_, buf)| buf).collect();
         }
         Branch::Right(c) => {
           // Restart data transfer process
@@ -78,18 +79,53 @@ pub fn tcp_server(c: Chan<(), TCPServer>) -> Vec<Buffer> {
   }
 }
 
+/// Builds the packets still owed to the receiver: every buffer whose
+/// `seqno` hasn't shown up in `delivered` yet.
+fn missing_packets(bufs: &Vec<Buffer>, delivered: &HashSet<usize>) -> Vec<Packet> {
+  bufs
+    .iter()
+    .enumerate()
+    .filter(|(seqno, _)| !delivered.contains(seqno))
+    .map(|(seqno, buf)| Packet {
+      buf: buf.clone(),
+      seqno,
+    })
+    .collect()
+}
+
 pub fn tcp_client(c: Chan<(), TCPClient>, bufs: Vec<Buffer>) {
-  // let mut c = c.send(Syn);
-  // let (mut c, syn_ack) = c.recv();
-  // c = c.send(Ack);
-
-  // for (seqno, buffer) in bufs.iter().enumerate() {
-  //   let packet = Packet {
-  //     buf: buffer.to_vec(),
-  //     seqno,
-  //   };
-  //   c.send(packet)
-  // }
+  // Handshake
+  let c = c.send(Syn);
+  let (c, syn_ack) = c.recv();
+  let c = c.send(Ack);
+
+  // Data transfer: keep retransmitting whatever hasn't been acked yet,
+  // since `NOISY` mode may drop any packet on a given round.
+  let mut delivered: HashSet<usize> = HashSet::new();
+  let mut to_send = missing_packets(&bufs, &delivered);
+
+  let mut c = c.rec_push();
+  loop {
+    c = {
+      let c = c.send(to_send.clone());
+      let (c, acked) = c.recv();
+      delivered.extend(acked);
+
+      if delivered.len() == bufs.len() {
+        // Every packet has been acknowledged; close the connection.
+        let c = c.left();
+        let (c, _ack) = c.recv();
+        let (c, _fin) = c.recv();
+        let c = c.send(Ack);
+        c.close();
+        return;
+      } else {
+        // Retransmit only the packets that are still missing.
+        to_send = missing_packets(&bufs, &delivered);
+        c.right().rec_pop()
+      }
+    }
+  }
 }
 
 #[cfg(test)]